@@ -0,0 +1,337 @@
+//! coroutine aware filesystem API
+//!
+//! these types mirror `std::fs` but never block the worker thread: the actual
+//! `open`/`read`/`write`/`seek`/`sync` syscalls are driven through the IO
+//! backend (Linux AIO or the portable blocking pool) while the calling
+//! coroutine parks. the [`OpenOptions`] builder exposes the `std` platform
+//! extensions for a creation `mode`, custom open flags and the Windows
+//! access/share/attribute knobs; `security_qos_flags` is not exposed.
+
+use std::collections::VecDeque;
+use std::ffi::{OsStr, OsString};
+use std::fs::{FileType, Metadata};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use io::sys::blocking::blocking;
+use io::sys::fs::{FileRead, FileWrite};
+
+// how many directory entries a single `readdir` offload pulls back, so that
+// only every Nth `next_entry` call actually suspends the coroutine
+const DIR_BATCH: usize = 32;
+
+/// a coroutine aware handle to an open file
+///
+/// cloning the handle is cheap and shares the same underlying descriptor, so
+/// independent coroutines may read and write the same file concurrently.
+#[derive(Clone)]
+pub struct File {
+    inner: Arc<std::fs::File>,
+}
+
+impl File {
+    fn from_std(file: std::fs::File) -> File {
+        File {
+            inner: Arc::new(file),
+        }
+    }
+
+    /// open a file in read-only mode, parking until the `open(2)` resolves
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+        OpenOptions::new().read(true).open(path)
+    }
+
+    /// open a file in write-only mode, creating or truncating it
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<File> {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+    }
+
+    /// flush all in-flight data and metadata to disk, parking until done
+    pub fn sync_all(&self) -> io::Result<()> {
+        FileWrite::new(self.inner.clone()).sync_all()
+    }
+
+    /// flush all in-flight data to disk without the extra metadata sync
+    pub fn sync_data(&self) -> io::Result<()> {
+        FileWrite::new(self.inner.clone()).sync_data()
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        FileRead::new(self.inner.clone()).read(buf)
+    }
+}
+
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        FileWrite::new(self.inner.clone()).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        FileWrite::new(self.inner.clone()).flush()
+    }
+}
+
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        FileRead::new(self.inner.clone()).seek(pos)
+    }
+}
+
+/// a builder for coroutine aware [`File`]s, the analogue of
+/// [`std::fs::OpenOptions`]
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    inner: std::fs::OpenOptions,
+}
+
+impl OpenOptions {
+    /// create a blank set of options, all flags initially `false`
+    pub fn new() -> OpenOptions {
+        OpenOptions {
+            inner: std::fs::OpenOptions::new(),
+        }
+    }
+
+    /// open the file for reading
+    pub fn read(&mut self, read: bool) -> &mut OpenOptions {
+        self.inner.read(read);
+        self
+    }
+
+    /// open the file for writing
+    pub fn write(&mut self, write: bool) -> &mut OpenOptions {
+        self.inner.write(write);
+        self
+    }
+
+    /// set the append mode, all writes go to the end of the file
+    pub fn append(&mut self, append: bool) -> &mut OpenOptions {
+        self.inner.append(append);
+        self
+    }
+
+    /// truncate the file to zero length on open
+    pub fn truncate(&mut self, truncate: bool) -> &mut OpenOptions {
+        self.inner.truncate(truncate);
+        self
+    }
+
+    /// create the file if it does not already exist
+    pub fn create(&mut self, create: bool) -> &mut OpenOptions {
+        self.inner.create(create);
+        self
+    }
+
+    /// create the file, failing if it already exists
+    pub fn create_new(&mut self, create_new: bool) -> &mut OpenOptions {
+        self.inner.create_new(create_new);
+        self
+    }
+
+    /// open the file, performing the blocking `open(2)`/`CreateFile` on a
+    /// worker thread and parking the coroutine until the path resolves
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<File> {
+        let options = self.inner.clone();
+        let path = path.as_ref().to_owned();
+        let file = blocking(move || options.open(path))?;
+        Ok(File::from_std(file))
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        OpenOptions::new()
+    }
+}
+
+#[cfg(unix)]
+impl OpenOptions {
+    /// set the mode bits a new file is created with (unix only)
+    pub fn mode(&mut self, mode: u32) -> &mut OpenOptions {
+        use std::os::unix::fs::OpenOptionsExt;
+        self.inner.mode(mode);
+        self
+    }
+
+    /// pass custom flags to the `flags` argument of `open(2)` (unix only)
+    pub fn custom_flags(&mut self, flags: i32) -> &mut OpenOptions {
+        use std::os::unix::fs::OpenOptionsExt;
+        self.inner.custom_flags(flags);
+        self
+    }
+}
+
+#[cfg(windows)]
+impl OpenOptions {
+    /// override the `dwDesiredAccess` passed to `CreateFile` (windows only)
+    pub fn access_mode(&mut self, access: u32) -> &mut OpenOptions {
+        use std::os::windows::fs::OpenOptionsExt;
+        self.inner.access_mode(access);
+        self
+    }
+
+    /// set the `dwShareMode` passed to `CreateFile` (windows only)
+    pub fn share_mode(&mut self, share: u32) -> &mut OpenOptions {
+        use std::os::windows::fs::OpenOptionsExt;
+        self.inner.share_mode(share);
+        self
+    }
+
+    /// pass custom `dwFlagsAndAttributes` flags to `CreateFile` (windows only)
+    pub fn custom_flags(&mut self, flags: u32) -> &mut OpenOptions {
+        use std::os::windows::fs::OpenOptionsExt;
+        self.inner.custom_flags(flags);
+        self
+    }
+
+    /// set the file attributes in `dwFlagsAndAttributes` (windows only)
+    pub fn attributes(&mut self, attributes: u32) -> &mut OpenOptions {
+        use std::os::windows::fs::OpenOptionsExt;
+        self.inner.attributes(attributes);
+        self
+    }
+}
+
+/// open a file in read-only mode, see [`File::open`]
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    File::open(path)
+}
+
+/// open a file in write-only mode, see [`File::create`]
+pub fn create<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    File::create(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_dir, DIR_BATCH};
+    use std::io::Write;
+
+    #[test]
+    fn streams_entries_across_batch_boundaries() {
+        let h = ::coroutine::Builder::new()
+            .spawn(|| {
+                let dir = ::std::env::temp_dir().join("may_read_dir_batching");
+                ::std::fs::create_dir_all(&dir).unwrap();
+
+                // more than one batch, so next_entry has to refill its buffer
+                let count = DIR_BATCH + 8;
+                for i in 0..count {
+                    let mut f = ::std::fs::File::create(dir.join(format!("f{}", i))).unwrap();
+                    f.write_all(b"x").unwrap();
+                }
+
+                let mut rd = read_dir(&dir).unwrap();
+                let mut seen = 0;
+                while let Some(entry) = rd.next_entry().unwrap() {
+                    assert!(entry.file_name().to_string_lossy().starts_with('f'));
+                    seen += 1;
+                }
+
+                ::std::fs::remove_dir_all(&dir).ok();
+                seen
+            })
+            .unwrap();
+        assert_eq!(h.join().unwrap(), DIR_BATCH + 8);
+    }
+}
+
+/// open a directory for coroutine-friendly streaming traversal
+///
+/// the returned [`ReadDir`] yields entries one at a time through
+/// [`ReadDir::next_entry`] without blocking the worker thread.
+pub fn read_dir<P: AsRef<Path>>(path: P) -> io::Result<ReadDir> {
+    let path = path.as_ref().to_owned();
+    let inner = blocking(move || std::fs::read_dir(path))?;
+    Ok(ReadDir {
+        inner: Some(inner),
+        buf: VecDeque::new(),
+    })
+}
+
+/// a coroutine aware directory iterator
+///
+/// `next_entry` pulls entries in batches of [`DIR_BATCH`] on the blocking pool
+/// and serves them from an internal buffer, so only every `DIR_BATCH`th call
+/// parks the coroutine while the underlying `readdir` runs.
+pub struct ReadDir {
+    // `None` once the underlying iterator has been exhausted
+    inner: Option<std::fs::ReadDir>,
+    buf: VecDeque<DirEntry>,
+}
+
+impl ReadDir {
+    /// return the next directory entry, parking the coroutine only when the
+    /// buffer has drained and a fresh batch must be read
+    pub fn next_entry(&mut self) -> io::Result<Option<DirEntry>> {
+        if let Some(entry) = self.buf.pop_front() {
+            return Ok(Some(entry));
+        }
+
+        let inner = match self.inner.take() {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+
+        // offload one batch worth of `readdir` calls to a pool thread
+        let (inner, entries) = blocking(move || {
+            let mut inner = inner;
+            let mut entries = Vec::with_capacity(DIR_BATCH);
+            for _ in 0..DIR_BATCH {
+                match inner.next() {
+                    Some(Ok(e)) => entries.push(DirEntry {
+                        path: e.path(),
+                        file_name: e.file_name(),
+                    }),
+                    Some(Err(e)) => return (Some(inner), Err(e)),
+                    None => return (None, Ok(entries)),
+                }
+            }
+            (Some(inner), Ok(entries))
+        });
+
+        self.inner = inner;
+        self.buf = entries?.into();
+        Ok(self.buf.pop_front())
+    }
+}
+
+/// a single entry returned by [`ReadDir::next_entry`]
+///
+/// `file_name` is captured cheaply during the batch read; `file_type` and
+/// `metadata` issue a `stat` and so are offloaded to the blocking pool too.
+pub struct DirEntry {
+    path: PathBuf,
+    file_name: OsString,
+}
+
+impl DirEntry {
+    /// the full path to the entry, including the directory that was opened
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// the bare file name of the entry
+    pub fn file_name(&self) -> &OsStr {
+        &self.file_name
+    }
+
+    /// the file type, following symlinks is avoided (mirrors `std`)
+    pub fn file_type(&self) -> io::Result<FileType> {
+        let path = self.path.clone();
+        blocking(move || std::fs::symlink_metadata(path).map(|m| m.file_type()))
+    }
+
+    /// the full metadata of the entry
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        let path = self.path.clone();
+        blocking(move || std::fs::metadata(path))
+    }
+}
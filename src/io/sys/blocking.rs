@@ -0,0 +1,136 @@
+//! a small bounded thread pool used to offload blocking syscalls
+//!
+//! on platforms without a coroutine-friendly file backend (everything but the
+//! Linux AIO path) the file operations are real blocking calls. running them
+//! on the worker threads would stall every coroutine sharing that worker, so
+//! instead each call is handed to a dedicated pool here and the calling
+//! coroutine parks on a [`Park`] until the result comes back — exactly how a
+//! coroutine waiting on a timer is resumed: the pool thread stores the result
+//! into an [`AtomicOption`] and calls [`Park::unpark`] to reschedule it.
+
+use std::sync::mpsc::{channel, Sender};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use config::config;
+use park::Park;
+use sync::AtomicOption;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// the fallback number of worker threads, used when `config` leaves the io
+// worker count at its `0` sentinel; kept small since these threads only exist
+// to absorb blocking syscalls rather than to do cpu work
+const DEFAULT_POOL_SIZE: usize = 8;
+
+struct Pool {
+    tx: Mutex<Sender<Job>>,
+}
+
+impl Pool {
+    fn new(size: usize) -> Pool {
+        let (tx, rx) = channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        for _ in 0..size {
+            let rx = rx.clone();
+            thread::spawn(move || loop {
+                let job = {
+                    let lock = rx.lock().unwrap();
+                    match lock.recv() {
+                        Ok(job) => job,
+                        // the sender is gone, the process is shutting down
+                        Err(_) => return,
+                    }
+                };
+                job();
+            });
+        }
+        Pool { tx: Mutex::new(tx) }
+    }
+
+    fn execute(&self, job: Job) {
+        // a send only fails if every worker has gone away, which never happens
+        // while the pool lives for the whole process lifetime
+        self.tx.lock().unwrap().send(job).ok();
+    }
+}
+
+lazy_static! {
+    // size the pool from the configured io worker count, falling back to
+    // `DEFAULT_POOL_SIZE` when it is left at the `0` sentinel
+    static ref POOL: Pool = Pool::new(match config().get_io_workers() {
+        0 => DEFAULT_POOL_SIZE,
+        n => n,
+    });
+}
+
+/// run a blocking closure on the pool and park the current coroutine until it
+/// finishes, returning the closure's result
+///
+/// the closure must be `Send` since it crosses to a pool thread; the result is
+/// shuttled back through an [`AtomicOption`] and the coroutine is resumed with
+/// [`Park::unpark`].
+pub fn blocking<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let park = Arc::new(Park::new());
+    park.ignore_cancel(true);
+    let ret = Arc::new(AtomicOption::none());
+
+    let w_park = park.clone();
+    let w_ret = ret.clone();
+    POOL.execute(Box::new(move || {
+        let r = f();
+        w_ret.swap(r, Ordering::Release);
+        w_park.unpark();
+    }));
+
+    // wait forever, the worker always unparks us exactly once when done
+    loop {
+        // a None timeout never returns Timeout, and we ignore cancellation here
+        // so that the spawned job is always observed to completion
+        let _ = park.park_timeout(None);
+        if let Some(r) = ret.take(Ordering::Acquire) {
+            return r;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::blocking;
+
+    #[test]
+    fn round_trips_the_result() {
+        let h = ::coroutine::Builder::new()
+            .spawn(|| blocking(|| 40usize + 2))
+            .unwrap();
+        assert_eq!(h.join().unwrap(), 42);
+    }
+
+    #[test]
+    fn offloads_blocking_file_io() {
+        use std::io::Write;
+
+        let path = ::std::env::temp_dir().join("may_blocking_round_trip.tmp");
+        let write_path = path.clone();
+        let read_path = path.clone();
+
+        let h = ::coroutine::Builder::new()
+            .spawn(move || {
+                blocking(move || {
+                    let mut f = ::std::fs::File::create(&write_path)?;
+                    f.write_all(b"hello from the pool")
+                })
+                .unwrap();
+                blocking(move || ::std::fs::read_to_string(&read_path)).unwrap()
+            })
+            .unwrap();
+
+        assert_eq!(h.join().unwrap(), "hello from the pool");
+        ::std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,106 @@
+//! portable file IO backend built on the blocking thread pool
+//!
+//! this mirrors the public surface of the Linux AIO backend in
+//! `io::sys::unix::fs` but works on any platform: every `read`/`write`/`seek`/
+//! `flush`/`sync_all` is offloaded to [`blocking`](super::super::blocking) so
+//! the calling coroutine parks instead of stalling its worker thread. the
+//! `io::sys` module picks this path on non Linux targets.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::super::blocking::blocking;
+
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let path = path.as_ref().to_owned();
+    blocking(move || File::open(path))
+}
+
+pub fn create<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let path = path.as_ref().to_owned();
+    blocking(move || File::create(path))
+}
+
+pub fn open_with_options<P: AsRef<Path>>(options: &mut OpenOptions, path: P) -> io::Result<File> {
+    let options = options.clone();
+    let path = path.as_ref().to_owned();
+    blocking(move || options.open(path))
+}
+
+/// coroutine aware read half of a file, offloaded to the blocking pool
+pub struct FileRead {
+    file: Arc<File>,
+}
+
+impl FileRead {
+    pub fn new(file: Arc<File>) -> FileRead {
+        FileRead { file }
+    }
+}
+
+impl Read for FileRead {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let file = self.file.clone();
+        let len = buf.len();
+        let data = blocking(move || {
+            let mut b = vec![0u8; len];
+            let n = (&*file).read(&mut b)?;
+            b.truncate(n);
+            io::Result::Ok(b)
+        })?;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+}
+
+impl Seek for FileRead {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let file = self.file.clone();
+        blocking(move || (&*file).seek(pos))
+    }
+}
+
+/// coroutine aware write half of a file, offloaded to the blocking pool
+pub struct FileWrite {
+    file: Arc<File>,
+}
+
+impl FileWrite {
+    pub fn new(file: Arc<File>) -> FileWrite {
+        FileWrite { file }
+    }
+
+    /// flush all in-flight data and metadata to disk, parking until done
+    pub fn sync_all(&self) -> io::Result<()> {
+        let file = self.file.clone();
+        blocking(move || file.sync_all())
+    }
+
+    /// flush all in-flight data to disk without the extra metadata sync
+    pub fn sync_data(&self) -> io::Result<()> {
+        let file = self.file.clone();
+        blocking(move || file.sync_data())
+    }
+}
+
+impl Write for FileWrite {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let file = self.file.clone();
+        let data = buf.to_owned();
+        blocking(move || (&*file).write(&data))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let file = self.file.clone();
+        blocking(move || (&*file).flush())
+    }
+}
+
+impl Seek for FileWrite {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let file = self.file.clone();
+        blocking(move || (&*file).seek(pos))
+    }
+}
@@ -0,0 +1,7 @@
+//! portable IO backend for targets without a native reactor file path
+//!
+//! selected by `io::sys` on every platform that is not the Linux AIO target,
+//! it drives file operations through the blocking thread pool (see
+//! [`fs`](self::fs)).
+
+pub mod fs;
@@ -0,0 +1,150 @@
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::Ordering;
+
+use super::{add_file, IoData};
+use cancel::Cancel;
+use coroutine_impl::{co_cancel_data, CoroutineImpl, EventSource};
+use scheduler::get_scheduler;
+use yield_now::{get_co_para, yield_with};
+
+/// a generic coroutine aware wrapper around an arbitrary pollable descriptor
+///
+/// unlike [`FileIo`](super::fs::FileIo) which is specialized around the AIO
+/// `eventfd`, `AsyncFd` registers *any* descriptor that is `AsRawFd` (pipes,
+/// unix sockets, char devices, inotify fds, custom protocol fds) with the
+/// scheduler's reactor so a coroutine can block until the kernel reports
+/// read/write readiness. the descriptor itself must be put into non blocking
+/// mode by the caller, `AsyncFd` only drives the readiness notifications.
+///
+/// the reactor here tracks a single combined readiness per descriptor (the
+/// `IoData` carries one `io_flag`/`co` slot shared by read and write), so
+/// `AsyncFd` exposes one [`AsyncFd::ready`] call rather than split read/write
+/// interest: it resolves as soon as the descriptor is readable *or* writable,
+/// and the caller disambiguates by attempting the non blocking operation.
+pub struct AsyncFd<T: AsRawFd> {
+    inner: T,
+    io: IoData,
+}
+
+impl<T: AsRawFd> AsyncFd<T> {
+    /// register `inner` with the reactor and return the wrapper
+    pub fn new(inner: T) -> io::Result<Self> {
+        let io = add_file(&inner)?;
+        Ok(AsyncFd { inner, io })
+    }
+
+    /// a shared reference to the wrapped descriptor
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// a mutable reference to the wrapped descriptor
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// consume the wrapper, deregister the descriptor and return it
+    ///
+    /// unlike dropping the wrapper after the descriptor is closed (which the
+    /// kernel removes from the poll set for us), `into_inner` hands the still
+    /// open descriptor back to the caller, so the reactor interest added in
+    /// [`AsyncFd::new`] must be torn down explicitly here.
+    pub fn into_inner(self) -> T {
+        let _ = get_scheduler().get_selector().del_fd(self.inner.as_raw_fd());
+        self.inner
+    }
+
+    /// block the current coroutine until the descriptor is readable
+    ///
+    /// the reactor tracks a single combined readiness per descriptor, so this
+    /// is a thin alias for [`AsyncFd::ready`] and may also return when the
+    /// descriptor is only writable; confirm by attempting the non blocking read
+    /// and clearing the guard on `WouldBlock`.
+    pub fn read_ready(&self) -> io::Result<ReadyGuard> {
+        self.ready()
+    }
+
+    /// block the current coroutine until the descriptor is writable
+    ///
+    /// the counterpart to [`AsyncFd::read_ready`] over the same combined
+    /// readiness, see [`AsyncFd::ready`].
+    pub fn write_ready(&self) -> io::Result<ReadyGuard> {
+        self.ready()
+    }
+
+    /// block the current coroutine until the descriptor is ready
+    ///
+    /// resolves as soon as the kernel reports the descriptor readable *or*
+    /// writable (see the note on [`AsyncFd`] about combined readiness). the
+    /// returned guard carries the readiness; when the following non blocking
+    /// operation reports `WouldBlock` the caller must call
+    /// [`ReadyGuard::clear_ready`] so the next `ready` re-arms the edge
+    /// triggered notification.
+    pub fn ready(&self) -> io::Result<ReadyGuard> {
+        // fast path: the reactor may already have flagged the descriptor
+        if !self.io.io_flag.swap(false, Ordering::Acquire) {
+            yield_with(&FdReady { io: &self.io });
+            // translate a cancellation delivered through the coroutine para
+            if let Some(err) = get_co_para() {
+                return Err(err);
+            }
+        }
+        Ok(ReadyGuard { io: &self.io })
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for AsyncFd<T> {
+    fn as_raw_fd(&self) -> ::std::os::unix::io::RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+/// a RAII token proving the descriptor was reported ready
+///
+/// after a non blocking operation returns `WouldBlock` on a ready
+/// descriptor the edge triggered readiness must be cleared so the reactor
+/// arms the next notification; drop the guard through [`ReadyGuard::clear_ready`]
+/// in that case. if the operation made progress simply let the guard drop.
+pub struct ReadyGuard<'a> {
+    io: &'a IoData,
+}
+
+impl<'a> ReadyGuard<'a> {
+    /// clear the current readiness, the next `*_ready` call will block again
+    pub fn clear_ready(self) {
+        self.io.io_flag.store(false, Ordering::Release);
+    }
+}
+
+// the event source used to park the coroutine on descriptor readiness, it
+// mirrors `Park` but drives the reactor's `IoData` instead of a plain flag
+struct FdReady<'a> {
+    io: &'a IoData,
+}
+
+impl<'a> EventSource for FdReady<'a> {
+    fn subscribe(&mut self, co: CoroutineImpl) {
+        let cancel = co_cancel_data(&co);
+        // register the coroutine on the io, the reactor will take it out and
+        // schedule it once the descriptor becomes ready
+        self.io.co.swap(co, Ordering::Release);
+
+        // re-check the readiness, the reactor may have fired while we were
+        // registering the coroutine above
+        if self.io.io_flag.load(Ordering::Acquire) {
+            self.io.schedule();
+            return;
+        }
+
+        // register the cancel data and re-check the cancel status
+        cancel.set_io(self.io.clone());
+        if cancel.is_canceled() {
+            unsafe { cancel.cancel() };
+        }
+    }
+
+    fn yield_back(&self, cancel: &'static Cancel) {
+        cancel.check_cancel();
+    }
+}
@@ -18,6 +18,30 @@ pub enum ParkError {
     Timeout,
 }
 
+/// the sentinel stored in [`SelectSlot::winner`] before any park fires
+pub const NO_WINNER: usize = usize::max_value();
+
+/// the shared waiter behind a [`Select`](::select::Select)
+///
+/// all the parks taking part in a select point at one of these instead of
+/// waking their own `wait_co`; the first park to fire claims the coroutine by
+/// winning the `winner` race and taking it out of the shared `co` slot.
+pub struct SelectSlot {
+    // the single coroutine blocked on the whole select
+    pub co: Arc<AtomicOption<CoroutineImpl>>,
+    // the id of the park that won, `NO_WINNER` until someone fires
+    pub winner: AtomicUsize,
+}
+
+impl SelectSlot {
+    pub fn new() -> Self {
+        SelectSlot {
+            co: Arc::new(AtomicOption::none()),
+            winner: AtomicUsize::new(NO_WINNER),
+        }
+    }
+}
+
 pub struct DropGuard<'a>(&'a Park);
 pub struct Park {
     // the coroutine that waiting for this park instance
@@ -35,6 +59,12 @@ pub struct Park {
     timeout_handle: AtomicPtr<TimeoutHandle<Arc<AtomicOption<CoroutineImpl>>>>,
     // a flag if kernel is entered
     wait_kernel: AtomicBool,
+    // when joined into a `Select`, points at the shared waiter; null otherwise.
+    // the pointed-to slot is owned by the `Select` and kept alive for the whole
+    // wait, tokens are cleared on resume before it is dropped
+    select_slot: AtomicPtr<SelectSlot>,
+    // this park's id within the owning select, used to race for `winner`
+    select_id: AtomicUsize,
 }
 
 // this is the park resource type (spmc style)
@@ -48,9 +78,43 @@ impl Park {
             timeout: AtomicUsize::new(0),
             timeout_handle: AtomicPtr::new(ptr::null_mut()),
             wait_kernel: AtomicBool::new(false),
+            select_slot: AtomicPtr::new(ptr::null_mut()),
+            select_id: AtomicUsize::new(NO_WINNER),
         }
     }
 
+    // join this park into a select, routing future unparks through the shared
+    // slot instead of the park's own `wait_co`
+    pub(crate) fn set_select(&self, slot: &Arc<SelectSlot>, id: usize) {
+        self.select_id.store(id, Ordering::Relaxed);
+        self.select_slot
+            .store(Arc::as_ptr(slot) as *mut SelectSlot, Ordering::Release);
+    }
+
+    // leave the select, a subsequent unpark falls back to the single-waiter path
+    pub(crate) fn clear_select(&self) {
+        self.select_slot.store(ptr::null_mut(), Ordering::Release);
+        self.select_id.store(NO_WINNER, Ordering::Relaxed);
+    }
+
+    // true if this park has already been signalled (its low state bit is set)
+    #[inline]
+    pub(crate) fn is_ready(&self) -> bool {
+        self.state.load(Ordering::Acquire) & 1 == 1
+    }
+
+    // consume a pending readiness bit left behind by `unpark`
+    //
+    // the normal `park_timeout` path clears this bit via `check_park` after the
+    // coroutine resumes; a `Select` resumes through the shared slot instead and
+    // never runs that path, so it must reset every fired park here. otherwise a
+    // park reused in a later select would observe a stale readiness and return
+    // without blocking.
+    #[inline]
+    pub(crate) fn reset_ready(&self) {
+        self.check_park();
+    }
+
     // ignore cancel, if true, caller have to do the check instead
     pub fn ignore_cancel(&self, ignore: bool) {
         self.check_cancel.store(!ignore, Ordering::Relaxed);
@@ -157,6 +221,28 @@ impl Park {
 
     #[inline]
     fn wake_up(&self, b_sync: bool) {
+        // if we are part of a select, race to become the winner and wake the
+        // shared coroutine; losers are no-ops
+        let slot = self.select_slot.load(Ordering::Acquire);
+        if !slot.is_null() {
+            let slot = unsafe { &*slot };
+            let id = self.select_id.load(Ordering::Relaxed);
+            if slot
+                .winner
+                .compare_exchange(NO_WINNER, id, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                if let Some(co) = slot.co.take_fast(Ordering::Acquire) {
+                    if b_sync {
+                        run_coroutine(co);
+                    } else {
+                        get_scheduler().schedule(co);
+                    }
+                }
+            }
+            return;
+        }
+
         if let Some(co) = self.wait_co.take_fast(Ordering::Acquire) {
             if b_sync {
                 run_coroutine(co);
@@ -214,6 +300,14 @@ impl Park {
         Ok(())
     }
 
+    // force the select wake path, used by `Select::subscribe` when a park is
+    // found already signalled and must win the race without going through the
+    // usual state-bit gate
+    #[inline]
+    pub(crate) fn select_wake_sync(&self) {
+        self.wake_up(true);
+    }
+
     fn delay_drop(&self) -> DropGuard {
         self.wait_kernel.store(true, Ordering::Release);
         DropGuard(self)
@@ -0,0 +1,187 @@
+//! wait on the first of several event sources to fire
+//!
+//! a bare [`Park`] is single-waiter: a coroutine can only block on one event at
+//! a time. `Select` lets one coroutine register against N parks and resume as
+//! soon as the first of them fires, returning the index of the winner. it is
+//! built on a shared [`SelectSlot`]: every participating park points at the
+//! same coroutine slot and races on a `winner` index, so exactly one park wakes
+//! the coroutine and the rest become no-ops.
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use cancel::Cancel;
+use coroutine_impl::{CoroutineImpl, EventSource};
+use park::{Park, SelectSlot, NO_WINNER};
+use yield_now::yield_with;
+
+/// wait for the first of several [`Park`]s to be unparked
+pub struct Select<'a> {
+    parks: Vec<&'a Park>,
+    slot: Arc<SelectSlot>,
+}
+
+impl<'a> Select<'a> {
+    /// create an empty selector
+    pub fn new() -> Self {
+        Select {
+            parks: Vec::new(),
+            slot: Arc::new(SelectSlot::new()),
+        }
+    }
+
+    /// add a park to the set, its index is the order in which it was added
+    pub fn add(&mut self, park: &'a Park) -> &mut Self {
+        self.parks.push(park);
+        self
+    }
+
+    /// block the current coroutine until one of the registered parks fires and
+    /// return its index
+    pub fn wait(&self) -> usize {
+        yield_with(self);
+
+        let winner = self.slot.winner.load(Ordering::Acquire);
+        // deregister every park so a later stray unpark can't wake us through
+        // the now-dropping shared slot, and consume any readiness bit left by a
+        // park that fired so it blocks again the next time it is selected on
+        for p in &self.parks {
+            p.clear_select();
+            p.reset_ready();
+        }
+        winner
+    }
+}
+
+impl<'a> Default for Select<'a> {
+    fn default() -> Self {
+        Select::new()
+    }
+}
+
+impl<'a> Drop for Select<'a> {
+    fn drop(&mut self) {
+        // `wait` clears the parks' slot pointers on its own cleanup path, but if
+        // the coroutine is canceled or panics while parked, `yield_back` unwinds
+        // the stack and that cleanup never runs. clear every park here so no
+        // live park is left pointing at the `SelectSlot` we are about to free;
+        // otherwise a later `unpark` would dereference freed memory in
+        // `wake_up`. the slot is a field of `self` and so still outlives this.
+        for p in &self.parks {
+            p.clear_select();
+        }
+    }
+}
+
+impl<'a> EventSource for Select<'a> {
+    fn subscribe(&mut self, co: CoroutineImpl) {
+        // install the single coroutine into the shared slot, then route every
+        // park through it
+        self.slot.co.swap(co, Ordering::Release);
+        for (id, p) in self.parks.iter().enumerate() {
+            p.set_select(&self.slot, id);
+        }
+
+        // re-check each park: a source signalled before we subscribed must win
+        // immediately rather than leaving the coroutine parked forever
+        for p in &self.parks {
+            if p.is_ready() {
+                return p.select_wake_sync();
+            }
+        }
+    }
+
+    fn yield_back(&self, cancel: &'static Cancel) {
+        cancel.check_cancel();
+    }
+}
+
+/// a [`Select`] that carries a caller supplied value for each source and
+/// returns the value of the winner instead of its index
+pub struct SelectMap<'a, T> {
+    select: Select<'a>,
+    values: Vec<T>,
+}
+
+impl<'a, T> SelectMap<'a, T> {
+    /// create an empty map-style selector
+    pub fn new() -> Self {
+        SelectMap {
+            select: Select::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// associate `value` with `park` and add it to the set
+    pub fn add(&mut self, park: &'a Park, value: T) -> &mut Self {
+        self.select.add(park);
+        self.values.push(value);
+        self
+    }
+
+    /// block until one of the parks fires and return the value associated with
+    /// the winner
+    pub fn wait(mut self) -> T {
+        let winner = self.select.wait();
+        debug_assert!(winner != NO_WINNER);
+        self.values.swap_remove(winner)
+    }
+}
+
+impl<'a, T> Default for SelectMap<'a, T> {
+    fn default() -> Self {
+        SelectMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Select;
+    use park::Park;
+
+    #[test]
+    fn returns_the_index_of_the_first_ready() {
+        let h = ::coroutine::Builder::new()
+            .spawn(|| {
+                let p0 = Park::new();
+                let p1 = Park::new();
+                // p1 is signalled before we block, it must win immediately
+                p1.unpark();
+                let mut s = Select::new();
+                s.add(&p0).add(&p1);
+                s.wait()
+            })
+            .unwrap();
+        assert_eq!(h.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn a_winning_park_blocks_again_when_reselected() {
+        let h = ::coroutine::Builder::new()
+            .spawn(|| {
+                let p0 = Park::new();
+                let p1 = Park::new();
+
+                // first round: p0 is the only ready source and wins
+                p0.unpark();
+                let first = {
+                    let mut s = Select::new();
+                    s.add(&p0).add(&p1);
+                    s.wait()
+                };
+
+                // second round: p0's readiness must have been consumed on
+                // resume, so only the freshly signalled p1 wins
+                p1.unpark();
+                let second = {
+                    let mut s = Select::new();
+                    s.add(&p0).add(&p1);
+                    s.wait()
+                };
+
+                (first, second)
+            })
+            .unwrap();
+        assert_eq!(h.join().unwrap(), (0, 1));
+    }
+}